@@ -0,0 +1,31 @@
+use std::env;
+use std::path::{Path, PathBuf};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let proto_root = PathBuf::from("proto");
+    let protos = collect_protos(&proto_root);
+
+    let descriptor_path =
+        PathBuf::from(env::var("OUT_DIR").unwrap()).join("echo_descriptor.bin");
+
+    tonic_build::configure()
+        .file_descriptor_set_path(&descriptor_path)
+        .compile(&protos, &[proto_root])?;
+
+    Ok(())
+}
+
+/// Recursively collect every `.proto` file under `dir`.
+fn collect_protos(dir: &Path) -> Vec<PathBuf> {
+    let mut protos = Vec::new();
+    for entry in std::fs::read_dir(dir).expect("proto directory should exist") {
+        let path = entry.expect("readable dir entry").path();
+        if path.is_dir() {
+            protos.extend(collect_protos(&path));
+        } else if path.extension().map(|ext| ext == "proto").unwrap_or(false) {
+            println!("cargo:rerun-if-changed={}", path.display());
+            protos.push(path);
+        }
+    }
+    protos
+}
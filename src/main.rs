@@ -1,19 +1,61 @@
 use api::echo_service_client::EchoServiceClient;
 use api::EchoRequest;
-use ::clap::{Parser};
+use ::clap::{Parser, ValueEnum};
+use tokio_stream::StreamExt;
+use tonic::metadata::MetadataValue;
+use std::time::Duration;
+use tonic::transport::{Certificate, Channel, ClientTlsConfig, Endpoint, Identity};
+use tonic::{Request, Status};
 
 pub mod api {
     tonic::include_proto!("api");
 }
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+enum Mode {
+    Unary,
+    ServerStream,
+    ClientStream,
+    Bidi,
+}
+
 #[derive(Parser)]
 #[command(author, version)]
 #[command(about = "echo - a simple CLI to send messages to a server", long_about = None)]
 struct ClientCli {
-    #[arg(short = 's', long = "server", default_value = "127.0.0.1")]
-    server: String,
+    /// One or more server hosts; repeat the flag or pass a comma-separated list to
+    /// load-balance requests across a replicated deployment
+    #[arg(short = 's', long = "server", default_value = "127.0.0.1", value_delimiter = ',')]
+    server: Vec<String>,
     #[arg(short = 'p', long = "port", default_value = "50052")]
     port: u16,
+    /// Which RPC shape to exercise
+    #[arg(short = 'm', long = "mode", value_enum, default_value = "unary")]
+    mode: Mode,
+    /// How many messages to send on the streaming modes
+    #[arg(short = 'c', long = "count", default_value = "5")]
+    count: usize,
+    /// PEM-encoded CA certificate used to verify the server; enables TLS when set
+    #[arg(long = "ca-cert")]
+    ca_cert: Option<String>,
+    /// PEM-encoded client certificate for mutual TLS
+    #[arg(long = "client-cert")]
+    client_cert: Option<String>,
+    /// PEM-encoded client private key for mutual TLS
+    #[arg(long = "client-key")]
+    client_key: Option<String>,
+    /// Override the domain name used for SNI/hostname verification
+    #[arg(long = "domain")]
+    domain: Option<String>,
+    /// Bearer token attached to the `authorization` metadata of every request
+    #[arg(long = "token")]
+    token: Option<String>,
+    /// Per-endpoint connect timeout in seconds
+    #[arg(long = "connect-timeout")]
+    connect_timeout: Option<u64>,
+    /// Per-endpoint request timeout in seconds
+    #[arg(long = "request-timeout")]
+    request_timeout: Option<u64>,
     /// The message to send
     message: String,
 }
@@ -23,15 +65,116 @@ struct ClientCli {
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cli = ClientCli::parse();
 
-    let mut client = EchoServiceClient::connect(format!("http://{}:{}", cli.server, cli.port)).await?;
+    if cli.client_cert.is_some() != cli.client_key.is_some() {
+        return Err("--client-cert and --client-key must be supplied together".into());
+    }
+
+    let tls = cli.ca_cert.is_some() || cli.client_cert.is_some();
+    let scheme = if tls { "https" } else { "http" };
+
+    let mut endpoints = Vec::with_capacity(cli.server.len());
+    for server in &cli.server {
+        let mut endpoint = Endpoint::from_shared(format!("{}://{}:{}", scheme, server, cli.port))?;
 
-    let request = tonic::Request::new(EchoRequest {
-        message: cli.message,
+        if tls {
+            let mut config = ClientTlsConfig::new();
+            if let Some(ca) = cli.ca_cert.as_ref() {
+                config = config.ca_certificate(Certificate::from_pem(std::fs::read(ca)?));
+            } else {
+                // No explicit CA: trust the platform roots so public/system-signed
+                // server certs still verify (e.g. client-cert-only mTLS).
+                config = config.with_native_roots();
+            }
+            if let (Some(cert), Some(key)) = (cli.client_cert.as_ref(), cli.client_key.as_ref()) {
+                config =
+                    config.identity(Identity::from_pem(std::fs::read(cert)?, std::fs::read(key)?));
+            }
+            if let Some(domain) = cli.domain.as_ref() {
+                config = config.domain_name(domain.clone());
+            }
+            endpoint = endpoint.tls_config(config)?;
+        }
+
+        if let Some(secs) = cli.connect_timeout {
+            endpoint = endpoint.connect_timeout(Duration::from_secs(secs));
+        }
+        if let Some(secs) = cli.request_timeout {
+            endpoint = endpoint.timeout(Duration::from_secs(secs));
+        }
+
+        endpoints.push(endpoint);
+    }
+
+    // Spread requests across the endpoints with tonic's power-of-two-choices
+    // balancer (not strict round-robin), lazily skipping any that fail to connect.
+    let channel = Channel::balance_list(endpoints.into_iter());
+    let token = cli.token.clone();
+    let mut client = EchoServiceClient::with_interceptor(channel, move |mut req: Request<()>| {
+        if let Some(token) = token.as_ref() {
+            let value = MetadataValue::try_from(format!("Bearer {}", token))
+                .map_err(|_| Status::invalid_argument("invalid auth token"))?;
+            req.metadata_mut().insert("authorization", value);
+        }
+        Ok(req)
     });
 
-    let response = client.echo(request).await?;
+    match cli.mode {
+        Mode::Unary => {
+            let request = tonic::Request::new(EchoRequest {
+                message: cli.message,
+                count: 0,
+            });
+
+            let response = client.echo(request).await?;
 
-    println!("RESPONSE={:?}", response.into_inner().message);
+            println!("RESPONSE={:?}", response.into_inner().message);
+        }
+        Mode::ServerStream => {
+            let request = tonic::Request::new(EchoRequest {
+                message: cli.message,
+                count: cli.count as u32,
+            });
+
+            let mut stream = client.server_streaming_echo(request).await?.into_inner();
+            while let Some(response) = stream.next().await {
+                println!("RESPONSE={:?}", response?.message);
+            }
+        }
+        Mode::ClientStream => {
+            let outbound = outbound_stream(cli.message, cli.count);
+
+            let response = client.client_streaming_echo(outbound).await?;
+
+            println!("RESPONSE={:?}", response.into_inner().message);
+        }
+        Mode::Bidi => {
+            let outbound = outbound_stream(cli.message, cli.count);
+
+            let mut stream = client
+                .bidirectional_streaming_echo(outbound)
+                .await?
+                .into_inner();
+            while let Some(response) = stream.next().await {
+                println!("RESPONSE={:?}", response?.message);
+            }
+        }
+    }
 
     Ok(())
 }
+
+/// Build an outbound request stream of `count` requests, each carrying `message`
+/// suffixed with its index (`"<message> #0"`, `"<message> #1"`, ...).
+fn outbound_stream(
+    message: String,
+    count: usize,
+) -> impl tokio_stream::Stream<Item = EchoRequest> {
+    async_stream::stream! {
+        for i in 0..count {
+            yield EchoRequest {
+                message: format!("{} #{}", message, i),
+                count: 0,
+            };
+        }
+    }
+}
@@ -1,12 +1,22 @@
-use tonic::{transport::Server, Request, Response, Status};
+use std::pin::Pin;
+
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::{Stream, StreamExt};
+use tonic::transport::{Certificate, Identity, Server, ServerTlsConfig};
+use tonic::{Request, Response, Status, Streaming};
 
 use api::echo_service_server::{EchoService, EchoServiceServer};
-use api::{EchoRequest, EchoResponse};
+use api::greeter_service_server::{GreeterService, GreeterServiceServer};
+use api::{EchoRequest, EchoResponse, HelloRequest, HelloResponse};
 
 use ::clap::{Parser};
 
 pub mod api {
     tonic::include_proto!("api");
+
+    /// Encoded `FileDescriptorSet` emitted by `build.rs` for server reflection.
+    pub const FILE_DESCRIPTOR_SET: &[u8] = tonic::include_file_descriptor_set!("echo_descriptor");
 }
 
 #[derive(Debug, Default)]
@@ -23,6 +33,114 @@ impl EchoService for Echo {
 
         Ok(Response::new(reply))
     }
+
+    type ServerStreamingEchoStream = ReceiverStream<Result<EchoResponse, Status>>;
+
+    async fn server_streaming_echo(
+        &self,
+        request: Request<EchoRequest>,
+    ) -> Result<Response<Self::ServerStreamingEchoStream>, Status> {
+        println!("Got a server-streaming request: {:?}", request);
+
+        let req = request.into_inner();
+        let message = req.message;
+        let count = req.count;
+        let (tx, rx) = mpsc::channel(4);
+
+        tokio::spawn(async move {
+            for _ in 0..count {
+                if tx
+                    .send(Ok(EchoResponse {
+                        message: message.clone(),
+                    }))
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    async fn client_streaming_echo(
+        &self,
+        request: Request<Streaming<EchoRequest>>,
+    ) -> Result<Response<EchoResponse>, Status> {
+        println!("Got a client-streaming request: {:?}", request);
+
+        let mut stream = request.into_inner();
+        let mut messages = Vec::new();
+        while let Some(req) = stream.next().await {
+            messages.push(req?.message);
+        }
+
+        let reply = EchoResponse {
+            message: messages.join(", "),
+        };
+
+        Ok(Response::new(reply))
+    }
+
+    type BidirectionalStreamingEchoStream =
+        Pin<Box<dyn Stream<Item = Result<EchoResponse, Status>> + Send>>;
+
+    async fn bidirectional_streaming_echo(
+        &self,
+        request: Request<Streaming<EchoRequest>>,
+    ) -> Result<Response<Self::BidirectionalStreamingEchoStream>, Status> {
+        println!("Got a bidirectional-streaming request: {:?}", request);
+
+        let mut stream = request.into_inner();
+        let (tx, rx) = mpsc::channel(4);
+
+        tokio::spawn(async move {
+            while let Some(req) = stream.next().await {
+                match req {
+                    Ok(req) => {
+                        if tx
+                            .send(Ok(EchoResponse {
+                                message: req.message,
+                            }))
+                            .await
+                            .is_err()
+                        {
+                            break;
+                        }
+                    }
+                    Err(status) => {
+                        let _ = tx.send(Err(status)).await;
+                        break;
+                    }
+                }
+            }
+        });
+
+        let out_stream = ReceiverStream::new(rx);
+        Ok(Response::new(
+            Box::pin(out_stream) as Self::BidirectionalStreamingEchoStream
+        ))
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Greeter {}
+
+#[tonic::async_trait]
+impl GreeterService for Greeter {
+    async fn say_hello(
+        &self,
+        request: Request<HelloRequest>,
+    ) -> Result<Response<HelloResponse>, Status> {
+        println!("Got a greeting request: {:?}", request);
+
+        let reply = HelloResponse {
+            message: format!("Hello, {}!", request.into_inner().name),
+        };
+
+        Ok(Response::new(reply))
+    }
 }
 
 #[derive(Parser)]
@@ -33,6 +151,22 @@ struct ServerCli {
     server: String,
     #[arg(short = 'p', long = "port", default_value = "50052")]
     port: u16,
+    /// PEM-encoded server certificate; enables TLS when set together with `--tls-key`
+    #[arg(long = "tls-cert")]
+    tls_cert: Option<String>,
+    /// PEM-encoded server private key
+    #[arg(long = "tls-key")]
+    tls_key: Option<String>,
+    /// PEM-encoded CA used to verify client certificates; enables mutual TLS
+    #[arg(long = "client-ca")]
+    client_ca: Option<String>,
+    /// Expose the gRPC server reflection service
+    #[arg(long = "enable-reflection")]
+    enable_reflection: bool,
+    /// Bearer token required on the `authorization` metadata of every echo and greeter
+    /// request; the health and reflection services are not checked
+    #[arg(long = "auth-token")]
+    auth_token: Option<String>,
 }
 
 
@@ -41,12 +175,68 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cli = ServerCli::parse();
     let addr = format!("{}:{}", cli.server, cli.port).parse()?;
     let echo = Echo::default();
+    let greeter = Greeter::default();
 
     println!("Server listening on {}", addr);
 
-    Server::builder()
-        .add_service(EchoServiceServer::new(echo))
-        .serve(addr)
+    let mut builder = Server::builder();
+
+    match (cli.tls_cert.as_ref(), cli.tls_key.as_ref()) {
+        (Some(cert), Some(key)) => {
+            let identity = Identity::from_pem(std::fs::read(cert)?, std::fs::read(key)?);
+            let mut tls = ServerTlsConfig::new().identity(identity);
+            if let Some(ca) = cli.client_ca.as_ref() {
+                tls = tls.client_ca_root(Certificate::from_pem(std::fs::read(ca)?));
+            }
+            builder = builder.tls_config(tls)?;
+        }
+        (None, None) => {
+            if cli.client_ca.is_some() {
+                return Err("--client-ca requires --tls-cert and --tls-key".into());
+            }
+        }
+        _ => return Err("--tls-cert and --tls-key must be supplied together".into()),
+    }
+
+    let (health_reporter, health_service) = tonic_health::server::health_reporter();
+    health_reporter
+        .set_serving::<EchoServiceServer<Echo>>()
+        .await;
+
+    let expected_token = cli.auth_token.clone();
+    let check_auth = move |req: Request<()>| -> Result<Request<()>, Status> {
+        let Some(expected) = expected_token.as_ref() else {
+            return Ok(req);
+        };
+        match req.metadata().get("authorization") {
+            Some(value) if value == format!("Bearer {}", expected).as_str() => Ok(req),
+            _ => Err(Status::unauthenticated("invalid or missing auth token")),
+        }
+    };
+
+    let echo_service = EchoServiceServer::with_interceptor(echo, check_auth.clone());
+    let greeter_service = GreeterServiceServer::with_interceptor(greeter, check_auth);
+
+    let mut router = builder
+        .add_service(health_service)
+        .add_service(echo_service)
+        .add_service(greeter_service);
+
+    if cli.enable_reflection {
+        let reflection = tonic_reflection::server::Builder::configure()
+            .register_encoded_file_descriptor_set(api::FILE_DESCRIPTOR_SET)
+            .build()?;
+        router = router.add_service(reflection);
+    }
+
+    // On Ctrl-C, flip health to NotServing so probes drain, then stop serving.
+    router
+        .serve_with_shutdown(addr, async move {
+            tokio::signal::ctrl_c().await.ok();
+            health_reporter
+                .set_not_serving::<EchoServiceServer<Echo>>()
+                .await;
+        })
         .await?;
 
     Ok(())